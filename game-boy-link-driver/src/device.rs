@@ -0,0 +1,19 @@
+/// A link-cable peripheral that can be emulated against a [`Peripheral`]-driven
+/// transport.
+///
+/// Each byte clocked in from the cable is handed to [`transfer`], which returns
+/// the byte to clock back out on the next exchange, or `None` to leave the
+/// reply at zero. Modelled on the stepping device traits common to emulator
+/// cores, it lets the event loop emulate any device — a printer, a second Game
+/// Boy, the Mobile Adapter GB — without duplicating the GPIO scaffolding.
+///
+/// [`Peripheral`]: crate::Peripheral
+/// [`transfer`]: LinkDevice::transfer
+pub trait LinkDevice {
+    /// Advance the device with the byte received this transfer, returning the
+    /// byte to reply with on the next one.
+    fn transfer(&mut self, received: u8) -> Option<u8>;
+
+    /// Return the device to its initial state, e.g. after the link goes idle.
+    fn reset(&mut self);
+}
@@ -1,9 +1,13 @@
 #![no_std]
 
 mod controller;
+mod device;
 mod peripheral;
+mod rle;
 
 pub use controller::*;
+pub use device::*;
 pub use peripheral::*;
+pub use rle::*;
 
 pub const BUF_LEN: usize = 2048;
@@ -0,0 +1,108 @@
+/// Incremental decoder for the Game Boy Printer run-length format.
+///
+/// Modelled on a streaming inflate: feed input chunks and drain the decoded
+/// bytes into a caller-provided output slice, keeping no allocation of its own
+/// so it can run on the `no_std` driver side. Call [`Decompressor::decompress`]
+/// repeatedly until both the input is exhausted and the decoder is [idle].
+///
+/// [idle]: Decompressor::is_idle
+#[derive(Debug, Default)]
+pub struct Decompressor {
+    state: State,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum State {
+    Control,
+    Repeat { run_len: usize },
+    Fill { byte: u8, remaining: usize },
+    Literal { remaining: usize },
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self::Control
+    }
+}
+
+impl Decompressor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Decode as much of `input` into `output` as fits, returning the number of
+    /// bytes consumed from `input` and produced into `output`. Stops early when
+    /// either slice is exhausted; the decoder remembers where it was so the
+    /// next call picks up mid-run.
+    pub fn decompress(&mut self, input: &[u8], output: &mut [u8]) -> (usize, usize) {
+        let mut consumed = 0;
+        let mut produced = 0;
+
+        loop {
+            match self.state {
+                State::Control => {
+                    if consumed == input.len() {
+                        break;
+                    }
+                    let control = input[consumed] as usize;
+                    consumed += 1;
+                    self.state = if control >> 7 == 1 {
+                        State::Repeat {
+                            run_len: (control & 0x7F) + 2,
+                        }
+                    } else {
+                        State::Literal {
+                            remaining: (control & 0x7F) + 1,
+                        }
+                    };
+                }
+                State::Repeat { run_len } => {
+                    if consumed == input.len() {
+                        break;
+                    }
+                    let byte = input[consumed];
+                    consumed += 1;
+                    self.state = State::Fill {
+                        byte,
+                        remaining: run_len,
+                    };
+                }
+                State::Fill { byte, remaining } => {
+                    if produced == output.len() {
+                        break;
+                    }
+                    output[produced] = byte;
+                    produced += 1;
+                    let remaining = remaining - 1;
+                    self.state = if remaining == 0 {
+                        State::Control
+                    } else {
+                        State::Fill { byte, remaining }
+                    };
+                }
+                State::Literal { remaining } => {
+                    if consumed == input.len() || produced == output.len() {
+                        break;
+                    }
+                    output[produced] = input[consumed];
+                    consumed += 1;
+                    produced += 1;
+                    let remaining = remaining - 1;
+                    self.state = if remaining == 0 {
+                        State::Control
+                    } else {
+                        State::Literal { remaining }
+                    };
+                }
+            }
+        }
+
+        (consumed, produced)
+    }
+
+    /// `true` when the decoder is between packets, i.e. not waiting on the rest
+    /// of a run. A stream that ends while this is `false` was truncated.
+    pub fn is_idle(&self) -> bool {
+        matches!(self.state, State::Control)
+    }
+}
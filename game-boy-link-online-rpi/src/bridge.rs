@@ -0,0 +1,197 @@
+use crate::framing::{self, Crc, Deframer, FrameKind};
+use crate::printer::AtomicSck;
+use crate::GameBoyLinkPins;
+use embedded_hal::digital::OutputPin;
+use futures::StreamExt;
+use game_boy_link_driver::Peripheral;
+use gpio_cdev::{EventRequestFlags, LineRequestFlags};
+use linux_embedded_hal::CdevPin;
+use std::{error::Error, net::SocketAddr, time::Duration, time::Instant};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+// The peripheral is reset once the local Game Boy has sent no SCK edge for one
+// second. Only SCK silence is timed: a peer that stops sending frames does not
+// by itself cause a reset, and no keepalive is exchanged.
+const IDLE_RESET: Duration = Duration::from_secs(1);
+
+// Bytes produced between flushes are coalesced into a single CRC frame to keep
+// the framing overhead off the wire. A pinned interval bounds the latency added
+// while a burst accumulates, and a cap flushes early once a batch is full.
+const FLUSH_INTERVAL: Duration = Duration::from_millis(4);
+const BATCH_MAX: usize = 64;
+
+/// Listen on `addr` and bridge the first Game Boy that connects to the local
+/// link cable. The local GB remains the clock master driving SCK.
+pub async fn serve(pins: GameBoyLinkPins, addr: SocketAddr) -> Result<(), Box<dyn Error>> {
+    let listener = TcpListener::bind(addr).await?;
+    println!("Listening for a link partner on {}", addr);
+    let (stream, peer) = listener.accept().await?;
+    println!("Link partner connected from {}", peer);
+    bridge(pins, stream).await
+}
+
+/// Connect to a remote bridge at `addr` and couple it to the local link cable.
+pub async fn connect(pins: GameBoyLinkPins, addr: SocketAddr) -> Result<(), Box<dyn Error>> {
+    let stream = TcpStream::connect(addr).await?;
+    println!("Connected to link partner at {}", addr);
+    bridge(pins, stream).await
+}
+
+// Drive the peripheral from SCK edges while tunnelling its byte stream over
+// `stream`. Each transfer shifts one byte in each direction simultaneously, so
+// every byte `recv` produces is paired with a byte the remote sends back:
+// outgoing bytes are written to the socket and incoming bytes are queued with
+// `push_back` to clock out on the next exchange. When the remote lags the
+// peripheral's `recv_buf` absorbs the slack and shifts out zeroes.
+async fn bridge(pins: GameBoyLinkPins, mut stream: TcpStream) -> Result<(), Box<dyn Error>> {
+    let mut sck_events = pins.sck.async_events(
+        LineRequestFlags::INPUT,
+        EventRequestFlags::BOTH_EDGES,
+        "game-boy-line-online",
+    )?;
+
+    // Emulated pin that is driven by events from the real SCK pin
+    let mut sck = AtomicSck::default();
+
+    let sin = CdevPin::new(pins.sin.request(LineRequestFlags::OUTPUT, 0, "game-boy-line-online")?)?;
+    let sout = CdevPin::new(pins.sout.request(LineRequestFlags::INPUT, 0, "game-boy-line-online")?)?;
+
+    let mut peripheral = Peripheral::new(sck.clone(), sin, sout);
+
+    let mut last_line_event_type = None;
+
+    let mut now = Instant::now();
+
+    // Every batch of link bytes is wrapped in a CRC frame so a corrupted or
+    // desynchronized stream is caught rather than silently shifting every
+    // subsequent transfer. The last batch is retained so it can be resent when
+    // the peer asks after a bad frame.
+    let crc = Crc::default();
+    let mut deframer = Deframer::new(crc);
+    let mut out_batch: Vec<u8> = vec![];
+    let mut last_batch: Vec<u8> = vec![];
+    let mut read_buf = [0; 256];
+
+    // A pinned interval whose deadline is not reset by the other `select!`
+    // branches, so a burst of SCK edges still flushes every `FLUSH_INTERVAL`
+    // instead of only when `out_batch` reaches `BATCH_MAX`.
+    let mut flush = tokio::time::interval(FLUSH_INTERVAL);
+
+    loop {
+        tokio::select! {
+            event = sck_events.next() => {
+                let event = match event {
+                    Some(event) => event,
+                    None => break,
+                };
+
+                // If some time passes, reset the peripheral
+                if now.elapsed() > IDLE_RESET {
+                    peripheral.reset();
+                }
+                now = Instant::now();
+
+                if let Ok(line_event) = event {
+                    // Sometimes there are duplicate events, debounce them
+                    let line_event_type = line_event.event_type();
+                    match &last_line_event_type {
+                        Some(llet) if llet == &line_event_type => {
+                            continue;
+                        }
+                        _ => {
+                            last_line_event_type.replace(line_event_type);
+                        }
+                    };
+
+                    match line_event.event_type() {
+                        gpio_cdev::EventType::FallingEdge => {
+                            sck.try_set_low()?;
+                        }
+                        gpio_cdev::EventType::RisingEdge => {
+                            sck.try_set_high()?;
+                        }
+                    };
+
+                    if let Some(byte) = peripheral.recv()? {
+                        out_batch.push(byte);
+                        // Flush early once the batch is full; otherwise the
+                        // flush tick coalesces the rest of the burst.
+                        if out_batch.len() >= BATCH_MAX {
+                            last_batch = core::mem::take(&mut out_batch);
+                            stream
+                                .write_all(&framing::encode(&crc, FrameKind::Data, &last_batch))
+                                .await?;
+                        }
+                    }
+                }
+            }
+            read = stream.read(&mut read_buf) => {
+                match read {
+                    // The remote hung up; nothing more will be clocked out.
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => {
+                        deframer.push(&read_buf[..n]);
+                        loop {
+                            match deframer.next_frame() {
+                                Ok(Some(frame)) => match frame.kind {
+                                    FrameKind::Data => {
+                                        for byte in frame.payload {
+                                            peripheral.push_back(byte);
+                                        }
+                                    }
+                                    FrameKind::ResendRequest => {
+                                        // Only answer if we actually have a batch
+                                        // to replay, so two freshly-started peers
+                                        // can't ping requests back and forth.
+                                        if !last_batch.is_empty() {
+                                            stream
+                                                .write_all(&framing::encode(
+                                                    &crc,
+                                                    FrameKind::Data,
+                                                    &last_batch,
+                                                ))
+                                                .await?;
+                                        }
+                                    }
+                                },
+                                Ok(None) => break,
+                                Err(err) => {
+                                    // Reset and ask the peer to resend; the
+                                    // deframer scans forward to the next sync
+                                    // word on its own.
+                                    eprintln!("{}; resyncing", err);
+                                    peripheral.reset();
+                                    stream
+                                        .write_all(&framing::encode(
+                                            &crc,
+                                            FrameKind::ResendRequest,
+                                            &[],
+                                        ))
+                                        .await?;
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            // Flush any coalesced bytes, and reset once SCK has been silent for
+            // `IDLE_RESET`; silence from the peer alone does not reset.
+            _ = flush.tick() => {
+                if !out_batch.is_empty() {
+                    last_batch = core::mem::take(&mut out_batch);
+                    stream
+                        .write_all(&framing::encode(&crc, FrameKind::Data, &last_batch))
+                        .await?;
+                }
+                if now.elapsed() > IDLE_RESET {
+                    peripheral.reset();
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
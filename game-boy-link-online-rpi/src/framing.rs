@@ -0,0 +1,235 @@
+use std::{error::Error, fmt};
+
+/// A configurable CRC used to detect corruption in framed link batches.
+///
+/// The width (in bits, a multiple of 8 up to 32) and polynomial are both
+/// parameters so the wire format can match whatever firmware link layer it is
+/// talking to. The default is CRC-16/CCITT, a common choice for such layers.
+#[derive(Debug, Clone, Copy)]
+pub struct Crc {
+    width: u32,
+    poly: u32,
+}
+
+impl Crc {
+    pub fn new(width: u32, poly: u32) -> Self {
+        debug_assert!(
+            (8..=32).contains(&width) && width % 8 == 0,
+            "CRC width must be a multiple of 8 in [8, 32]",
+        );
+        Self { width, poly }
+    }
+
+    fn mask(&self) -> u32 {
+        if self.width >= 32 {
+            u32::MAX
+        } else {
+            (1 << self.width) - 1
+        }
+    }
+
+    fn width_bytes(&self) -> usize {
+        (self.width / 8) as usize
+    }
+
+    /// Compute the CRC of `data`, MSB-first with no reflection.
+    pub fn checksum(&self, data: &[u8]) -> u32 {
+        let top = 1 << (self.width - 1);
+        let mask = self.mask();
+        let mut crc = 0;
+
+        for &byte in data {
+            crc ^= (byte as u32) << (self.width - 8);
+            for _ in 0..8 {
+                crc = if crc & top != 0 {
+                    (crc << 1) ^ self.poly
+                } else {
+                    crc << 1
+                };
+                crc &= mask;
+            }
+        }
+
+        crc
+    }
+}
+
+impl Default for Crc {
+    fn default() -> Self {
+        Self::new(16, 0x1021)
+    }
+}
+
+/// Raised when a received frame's trailing CRC does not match its contents.
+#[derive(Debug, Clone, Copy)]
+pub struct ChecksumMismatch {
+    pub expected: u32,
+    pub actual: u32,
+}
+
+impl fmt::Display for ChecksumMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Frame checksum mismatch: expected {:#x}, computed {:#x}",
+            self.expected, self.actual
+        )
+    }
+}
+
+impl Error for ChecksumMismatch {}
+
+/// A two-byte sync word leading every frame, so a receiver that has lost the
+/// stream can scan forward and re-establish the frame boundary.
+const SYNC: [u8; 2] = [0xA5, 0x5A];
+
+/// Upper bound on a frame's payload length. A length prefix larger than this is
+/// treated as corruption and skipped rather than blocking on up to 64 KB of
+/// bytes that will never arrive.
+const MAX_PAYLOAD: usize = 4096;
+
+/// The kind of frame, carried as an explicit type byte so a data frame and a
+/// resend request are never confused (an empty data batch is still a data
+/// frame, not a request).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameKind {
+    /// Carries a batch of link bytes.
+    Data,
+    /// Asks the peer to re-transmit its last data batch.
+    ResendRequest,
+}
+
+impl FrameKind {
+    fn from_u8(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(Self::Data),
+            1 => Some(Self::ResendRequest),
+            _ => None,
+        }
+    }
+}
+
+/// A decoded frame: its kind and, for data frames, its payload.
+#[derive(Debug, Clone)]
+pub struct Frame {
+    pub kind: FrameKind,
+    pub payload: Vec<u8>,
+}
+
+/// Encode a frame: the sync word, a type byte, a little-endian `u16` length
+/// prefix, the payload, and a trailing little-endian CRC over everything after
+/// the sync word.
+pub fn encode(crc: &Crc, kind: FrameKind, payload: &[u8]) -> Vec<u8> {
+    let mut body = Vec::with_capacity(3 + payload.len());
+    body.push(kind as u8);
+    body.extend_from_slice(&(payload.len() as u16).to_le_bytes());
+    body.extend_from_slice(payload);
+
+    let checksum = crc.checksum(&body);
+
+    let mut frame = Vec::with_capacity(SYNC.len() + body.len() + crc.width_bytes());
+    frame.extend_from_slice(&SYNC);
+    frame.extend_from_slice(&body);
+    frame.extend_from_slice(&checksum.to_le_bytes()[..crc.width_bytes()]);
+
+    frame
+}
+
+/// Incrementally reassembles frames from a byte stream, validating each CRC and
+/// resynchronizing on the sync word when the stream is shifted or corrupted.
+pub struct Deframer {
+    crc: Crc,
+    buf: Vec<u8>,
+}
+
+impl Deframer {
+    pub fn new(crc: Crc) -> Self {
+        Self { crc, buf: vec![] }
+    }
+
+    /// Append freshly received bytes to the reassembly buffer.
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.buf.extend_from_slice(bytes);
+    }
+
+    /// Pop the next complete frame. Returns `Ok(None)` when more bytes are
+    /// needed, and `Err` on a CRC mismatch — in which case the offending sync
+    /// word has been skipped so a subsequent call scans forward to the next
+    /// frame boundary, letting the receiver reset and request a resend.
+    pub fn next_frame(&mut self) -> Result<Option<Frame>, ChecksumMismatch> {
+        // Header is the sync word, the type byte and the length prefix.
+        const HEADER: usize = SYNC.len() + 1 + 2;
+
+        loop {
+            // Realign onto the sync word, dropping any garbage before it.
+            if self.buf.len() < SYNC.len() {
+                return Ok(None);
+            }
+            if self.buf[..SYNC.len()] != SYNC {
+                match self.find_sync() {
+                    Some(pos) => {
+                        self.buf.drain(..pos);
+                    }
+                    None => {
+                        // Keep only a trailing byte that might begin a sync word
+                        // split across two reads.
+                        let keep = SYNC.len() - 1;
+                        let cut = self.buf.len() - keep;
+                        self.buf.drain(..cut);
+                        return Ok(None);
+                    }
+                }
+                continue;
+            }
+
+            if self.buf.len() < HEADER {
+                return Ok(None);
+            }
+
+            let len = u16::from_le_bytes([self.buf[3], self.buf[4]]) as usize;
+            if len > MAX_PAYLOAD {
+                // A bogus length can only be corruption; skip this sync word and
+                // rescan for the real boundary.
+                self.buf.drain(..SYNC.len());
+                continue;
+            }
+
+            let body_end = HEADER + len;
+            let frame_len = body_end + self.crc.width_bytes();
+            if self.buf.len() < frame_len {
+                return Ok(None);
+            }
+
+            let expected = {
+                let mut bytes = [0; 4];
+                bytes[..self.crc.width_bytes()].copy_from_slice(&self.buf[body_end..frame_len]);
+                u32::from_le_bytes(bytes)
+            };
+            let actual = self.crc.checksum(&self.buf[SYNC.len()..body_end]);
+
+            if expected != actual {
+                self.buf.drain(..SYNC.len());
+                return Err(ChecksumMismatch { expected, actual });
+            }
+
+            let kind = match FrameKind::from_u8(self.buf[2]) {
+                Some(kind) => kind,
+                None => {
+                    // Valid CRC but unknown type: consume and ignore it.
+                    self.buf.drain(..frame_len);
+                    continue;
+                }
+            };
+            let payload = self.buf[HEADER..body_end].to_vec();
+            self.buf.drain(..frame_len);
+            return Ok(Some(Frame { kind, payload }));
+        }
+    }
+
+    // Index of the next sync word in the buffer, if any.
+    fn find_sync(&self) -> Option<usize> {
+        self.buf
+            .windows(SYNC.len())
+            .position(|window| window == SYNC)
+    }
+}
@@ -1,9 +1,13 @@
+mod bridge;
+mod capture;
+mod framing;
 mod printer;
+mod render;
 
 use gpio_cdev::{Chip, Line};
 use lazy_static::lazy_static;
 use rppal::system::{DeviceInfo, Model};
-use std::{env, error::Error, time::Duration};
+use std::{env, error::Error, net::SocketAddr, time::Duration};
 
 lazy_static! {
     // Game Boy clock (SCK) speed is 8192 Hz
@@ -72,6 +76,8 @@ const SOUT_PIN_ENV_VAR: &str = "SOUT_PIN";
 const SD_PIN_ENV_VAR: &str = "SD_PIN";
 
 const MODE_ENV_VAR: &str = "MODE";
+const ADDR_ENV_VAR: &str = "ADDR";
+const CAPTURE_ENV_VAR: &str = "CAPTURE";
 
 pub struct GameBoyLinkPins {
     sck: Line,
@@ -154,7 +160,29 @@ async fn main() -> Result<(), Box<dyn Error>> {
     match mode.as_str() {
         "printer" => {
             let pins = GameBoyLinkPins::from_env_vars()?;
-            printer::main_loop(pins);
+            // Optionally tee the session to a capture file for later replay.
+            match env::var(CAPTURE_ENV_VAR) {
+                Ok(path) => {
+                    let writer = capture::CaptureWriter::create(path)?;
+                    let device = capture::Recording::new(printer::Printer::new(), writer);
+                    printer::run(pins, device).await?;
+                }
+                Err(_) => printer::main_loop(pins).await?,
+            }
+        }
+        "replay" => {
+            let path = env::var(CAPTURE_ENV_VAR)?;
+            capture::replay(path, printer::Printer::new())?;
+        }
+        "serve" => {
+            let pins = GameBoyLinkPins::from_env_vars()?;
+            let addr = env::var(ADDR_ENV_VAR)?.parse::<SocketAddr>()?;
+            bridge::serve(pins, addr).await?;
+        }
+        "connect" => {
+            let pins = GameBoyLinkPins::from_env_vars()?;
+            let addr = env::var(ADDR_ENV_VAR)?.parse::<SocketAddr>()?;
+            bridge::connect(pins, addr).await?;
         }
         "pokemon_trade" => {
             // loop {
@@ -1,8 +1,9 @@
+use crate::render::PrinterImage;
 use crate::GameBoyLinkPins;
 use bitflags::bitflags;
 use embedded_hal::digital::{InputPin, OutputPin, PinState};
 use futures::StreamExt;
-use game_boy_link_driver::Peripheral;
+use game_boy_link_driver::{Decompressor, LinkDevice, Peripheral};
 use gpio_cdev::{EventRequestFlags, LineRequestFlags};
 use linux_embedded_hal::CdevPin;
 use num::FromPrimitive;
@@ -297,42 +298,172 @@ bitflags! {
 }
 
 pub fn decompress_data(bytes: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut decoder = Decompressor::new();
     let mut decompressed_data = vec![];
+    let mut buf = [0; 256];
+    let mut consumed = 0;
 
-    let byte_iter = &mut bytes.iter();
+    loop {
+        let (c, p) = decoder.decompress(&bytes[consumed..], &mut buf);
+        consumed += c;
+        decompressed_data.extend_from_slice(&buf[..p]);
 
-    while let Some(run_byte) = byte_iter.next() {
-        let run_byte = *run_byte as usize;
-        let compressed_run = (run_byte >> 7) == 1;
+        // No more input was taken and no more output produced: we are done.
+        if c == 0 && p == 0 {
+            break;
+        }
+    }
 
-        if compressed_run {
-            let run_len = !(!run_byte | (1 << 7)) + 2;
+    if !decoder.is_idle() {
+        return Err(<Box<dyn Error>>::from(
+            "Compressed run ended before all of its bytes arrived.",
+        ));
+    }
 
-            if let Some(repeated_byte) = byte_iter.next() {
-                decompressed_data.extend(vec![*repeated_byte; run_len]);
-            } else {
-                return Err(<Box<dyn Error>>::from(
-                    "Repeat byte not found in compressed run.",
-                ));
+    Ok(decompressed_data)
+}
+
+/// Compress `bytes` into the run-length format [`decompress_data`] consumes.
+///
+/// Runs of 2..=129 identical bytes become a control byte with the high bit set
+/// whose low 7 bits hold `run_len - 2`, followed by the repeated byte; anything
+/// else is emitted as literal runs of 1..=128 bytes prefixed by a control byte
+/// with the high bit clear whose low 7 bits hold `len - 1`.
+pub fn compress_data(bytes: &[u8]) -> Vec<u8> {
+    let mut compressed_data = vec![];
+    let mut index = 0;
+
+    while index < bytes.len() {
+        let byte = bytes[index];
+
+        let mut run_len = 1;
+        while index + run_len < bytes.len() && bytes[index + run_len] == byte && run_len < 129 {
+            run_len += 1;
+        }
+
+        if run_len >= 2 {
+            compressed_data.push(0x80 | (run_len - 2) as u8);
+            compressed_data.push(byte);
+            index += run_len;
+        } else {
+            // Gather a literal run, stopping where a worthwhile repeat begins.
+            let start = index;
+            while index < bytes.len()
+                && index - start < 128
+                && !(index + 1 < bytes.len() && bytes[index] == bytes[index + 1])
+            {
+                index += 1;
+            }
+            compressed_data.push((index - start - 1) as u8);
+            compressed_data.extend_from_slice(&bytes[start..index]);
+        }
+    }
+
+    compressed_data
+}
+
+/// A Game Boy Printer emulated as a [`LinkDevice`]: it owns the
+/// [`PrinterState`] machine and the reply-byte logic that used to be inlined in
+/// the event loop, plus the framebuffer that accumulates across `Data` packets.
+pub struct Printer {
+    state: PrinterState,
+    image: PrinterImage,
+}
+
+impl Printer {
+    pub fn new() -> Self {
+        Self {
+            state: PrinterState::Magic0,
+            image: PrinterImage::new(),
+        }
+    }
+
+    // Consume a finished packet, rendering or buffering it as its command asks.
+    fn finish(&mut self, cmd: PrintCommand, compression: PrintCompression, payload: Vec<u8>) {
+        let payload = if let PrintCompression::Compressed = compression {
+            match decompress_data(&payload) {
+                Ok(payload) => payload,
+                Err(err) => {
+                    eprintln!("Failed to decompress printer payload: {}", err);
+                    return;
+                }
             }
         } else {
-            let run_len = run_byte + 1;
-            let run_bytes = byte_iter.take(run_len);
-
-            if run_bytes.len() == run_len {
-                decompressed_data.extend(run_bytes);
-            } else {
-                return Err(<Box<dyn Error>>::from(
-                    "Not enough bytes found in uncompressed run.",
-                ));
+            payload
+        };
+
+        match cmd {
+            PrintCommand::Init => self.image = PrinterImage::new(),
+            PrintCommand::Data => self.image.feed(&payload),
+            PrintCommand::Print => match self.image.print(&payload) {
+                Ok(Some(path)) => println!("Saved print to {}", path),
+                Ok(None) => {}
+                Err(err) => eprintln!("Failed to save print: {}", err),
+            },
+            PrintCommand::Status => {}
+        }
+    }
+}
+
+impl Default for Printer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LinkDevice for Printer {
+    fn transfer(&mut self, received: u8) -> Option<u8> {
+        self.state = match core::mem::replace(&mut self.state, PrinterState::Magic0)
+            .transition(received)
+        {
+            Ok(state) => state,
+            Err(err) => {
+                eprintln!("Printer protocol error: {}", err);
+                return None;
+            }
+        };
+
+        match self.state {
+            PrinterState::Keepalive { .. } => Some(PrinterAlive::Alive as u8),
+            PrinterState::Status { checksum_error, .. } => {
+                let status = if checksum_error {
+                    PrinterStatus::CHECKSUM_ERROR
+                } else {
+                    PrinterStatus::OK
+                };
+                Some(status.bits())
+            }
+            PrinterState::Done { .. } => {
+                if let PrinterState::Done {
+                    cmd,
+                    compression,
+                    payload,
+                    ..
+                } = core::mem::replace(&mut self.state, PrinterState::Magic0)
+                {
+                    self.finish(cmd, compression, payload);
+                }
+                None
             }
+            _ => None,
         }
     }
 
-    Ok(decompressed_data)
+    fn reset(&mut self) {
+        self.state = PrinterState::Magic0;
+    }
 }
 
 pub async fn main_loop(pins: GameBoyLinkPins) -> Result<(), Box<dyn Error>> {
+    run(pins, Printer::new()).await
+}
+
+/// Drive any [`LinkDevice`] from the real SCK pin's edge events, handling the
+/// GPIO, debounce and idle-reset scaffolding once for every emulated device.
+pub async fn run<D: LinkDevice>(
+    pins: GameBoyLinkPins,
+    mut device: D,
+) -> Result<(), Box<dyn Error>> {
     let mut sck_events = pins.sck.async_events(
         LineRequestFlags::INPUT,
         EventRequestFlags::BOTH_EDGES,
@@ -355,8 +486,6 @@ pub async fn main_loop(pins: GameBoyLinkPins) -> Result<(), Box<dyn Error>> {
 
     let mut peripheral = Peripheral::new(sck.clone(), sin, sout);
 
-    let mut printer_state = PrinterState::Magic0;
-
     let mut last_line_event_type = None;
 
     let mut now = Instant::now();
@@ -367,6 +496,7 @@ pub async fn main_loop(pins: GameBoyLinkPins) -> Result<(), Box<dyn Error>> {
             let elapsed = now.elapsed();
             if elapsed.as_secs() > 1 {
                 peripheral.reset();
+                device.reset();
                 // println!("Reset the peripheral");
             }
             if elapsed.as_micros() > 200 {
@@ -400,37 +530,9 @@ pub async fn main_loop(pins: GameBoyLinkPins) -> Result<(), Box<dyn Error>> {
 
                 if let Some(byte) = peripheral.recv()? {
                     // dbg!(&byte);
-                    printer_state = printer_state.transition(byte)?;
-                    match printer_state {
-                        PrinterState::Keepalive { .. } => {
-                            peripheral.push_back(PrinterAlive::Alive as u8);
-                        }
-                        PrinterState::Status { checksum_error, .. } => {
-                            let status = if checksum_error {
-                                PrinterStatus::CHECKSUM_ERROR
-                            } else {
-                                PrinterStatus::OK
-                            };
-                            peripheral.push_back(status.bits());
-                        }
-                        PrinterState::Done {
-                            compression,
-                            payload,
-                            ..
-                        } => {
-                            printer_state = PrinterState::Magic0;
-
-                            let mut payload = payload;
-
-                            if let PrintCompression::Compressed = compression {
-                                payload = decompress_data(&payload)?;
-                            }
-
-                            // TODO: save image
-                            println!("{:X?}", payload);
-                        }
-                        _ => {}
-                    };
+                    if let Some(reply) = device.transfer(byte) {
+                        peripheral.push_back(reply);
+                    }
                 }
             }
         } else {
@@ -442,7 +544,7 @@ pub async fn main_loop(pins: GameBoyLinkPins) -> Result<(), Box<dyn Error>> {
 }
 
 #[derive(Debug, Default, Clone)]
-struct AtomicSck {
+pub(crate) struct AtomicSck {
     is_high: Rc<AtomicBool>,
 }
 
@@ -478,3 +580,30 @@ impl InputPin for AtomicSck {
         Ok(!self.is_high.load(Ordering::Acquire))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{compress_data, decompress_data};
+
+    // Round-trip every input through the encoder and back, exercising the run
+    // length boundaries the format hinges on: compressed runs of 2..=129 and
+    // literal runs of 1..=128.
+    #[test]
+    fn compress_round_trips() {
+        let cases: Vec<Vec<u8>> = vec![
+            vec![],
+            vec![0x42],
+            vec![0xAA, 0xAA],             // shortest compressed run
+            vec![0xBB; 129],              // longest compressed run
+            vec![0x11; 130],             // spills into a second run
+            (0..128).collect(),          // longest single literal run
+            (0..129).collect(),          // spills into a second literal run
+            vec![0x00, 0x01, 0x01, 0x01, 0x02, 0x03, 0x03, 0x04],
+        ];
+
+        for case in cases {
+            let round_tripped = decompress_data(&compress_data(&case)).unwrap();
+            assert_eq!(round_tripped, case);
+        }
+    }
+}
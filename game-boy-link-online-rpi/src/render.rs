@@ -0,0 +1,101 @@
+use image::{GrayImage, Luma};
+use std::error::Error;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+// Monotonic sheet counter shared across every print in the process, so a
+// multi-photo session (the printer sends `Init` before each photo) never
+// reuses a filename and overwrites an earlier print.
+static NEXT_SHEET: AtomicU32 = AtomicU32::new(0);
+
+// Game Boy tiles are 8x8 pixels encoded as 2 bits per pixel, and the printer
+// lays them out 20 tiles (160px) wide, left-to-right then top-to-bottom.
+const TILE_SIDE: usize = 8;
+const BYTES_PER_TILE: usize = 16;
+const TILES_PER_ROW: usize = 20;
+const IMAGE_WIDTH: usize = TILES_PER_ROW * TILE_SIDE;
+
+// Each unit of margin is one tile row of blank paper.
+const MARGIN_UNIT: usize = TILE_SIDE;
+// The palette maps each 2-bit pixel value to one of four shades, lightest
+// first. Darker values shift the grayscale level down.
+const SHADES: [u8; 4] = [0xFF, 0xAA, 0x55, 0x00];
+
+/// Accumulates 2bpp tile data across successive `Data` packets and renders it
+/// to a PNG when a `Print` command arrives.
+#[derive(Default)]
+pub struct PrinterImage {
+    data: Vec<u8>,
+}
+
+impl PrinterImage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append the decompressed payload of a `Data` packet to the framebuffer.
+    pub fn feed(&mut self, payload: &[u8]) {
+        self.data.extend_from_slice(payload);
+    }
+
+    /// Render the accumulated tiles as a PNG, applying the palette and margins
+    /// carried by the `Print` command's four instruction bytes, then clear the
+    /// framebuffer for the next sheet. Returns the path written, or `None` when
+    /// there is nothing buffered to print.
+    pub fn print(&mut self, instructions: &[u8]) -> Result<Option<String>, Box<dyn Error>> {
+        let tiles = self.data.len() / BYTES_PER_TILE;
+
+        // Nothing buffered, or only a truncated sub-tile remnant: there is no
+        // whole tile to render, so skip rather than asking the encoder to save
+        // a zero-height PNG (which it rejects).
+        if tiles == 0 {
+            self.data.clear();
+            return Ok(None);
+        }
+
+        let margins = instructions.get(1).copied().unwrap_or(0);
+        let palette = instructions.get(2).copied().unwrap_or(0xE4);
+        let top_margin = (margins >> 4) as usize * MARGIN_UNIT;
+        let bottom_margin = (margins & 0x0F) as usize * MARGIN_UNIT;
+
+        let tile_rows = tiles.div_ceil(TILES_PER_ROW);
+        let content_height = tile_rows * TILE_SIDE;
+        let height = top_margin + content_height + bottom_margin;
+
+        let mut image = GrayImage::from_pixel(
+            IMAGE_WIDTH as u32,
+            height as u32,
+            Luma([SHADES[0]]),
+        );
+
+        for tile in 0..tiles {
+            let tile_x = (tile % TILES_PER_ROW) * TILE_SIDE;
+            let tile_y = top_margin + (tile / TILES_PER_ROW) * TILE_SIDE;
+            let base = tile * BYTES_PER_TILE;
+
+            for row in 0..TILE_SIDE {
+                let low = self.data[base + row * 2];
+                let high = self.data[base + row * 2 + 1];
+
+                for col in 0..TILE_SIDE {
+                    let bit = 7 - col;
+                    let value =
+                        (((high >> bit) & 1) << 1) | ((low >> bit) & 1);
+                    let shade = SHADES[((palette >> (value * 2)) & 0x3) as usize];
+                    image.put_pixel(
+                        (tile_x + col) as u32,
+                        (tile_y + row) as u32,
+                        Luma([shade]),
+                    );
+                }
+            }
+        }
+
+        let sheet = NEXT_SHEET.fetch_add(1, Ordering::Relaxed);
+        let path = format!("printout-{}.png", sheet);
+        image.save(&path)?;
+
+        self.data.clear();
+
+        Ok(Some(path))
+    }
+}